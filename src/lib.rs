@@ -1,5 +1,19 @@
 use std::{collections::HashMap, time::SystemTime};
 
+/// Standard IEEE CRC32 (poly 0xEDB88320, reflected, init/final XOR 0xFFFFFFFF),
+/// used to derive Steam's non-Steam-game AppIDs.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFFFFFF;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB88320 & mask);
+        }
+    }
+    !crc
+}
+
 #[derive(Debug, Clone)]
 pub struct Shortcut {
     id: u32,
@@ -65,15 +79,261 @@ impl Shortcut {
             tags,
         }
     }
+
+    /// Writes `shortcuts` out as a binary `shortcuts.vdf` at `path`, in the
+    /// same layout `parser::Parser` reads.
+    pub fn write_all(path: &str, shortcuts: &[Shortcut]) -> Result<(), std::io::Error> {
+        parser::write_all(path, shortcuts)
+    }
+
+    /// Steam's 32-bit non-Steam-game AppID: a CRC32 of the quoted exe path
+    /// concatenated with the app name, with the high bit set. This is the id
+    /// used to locate grid/header artwork for the shortcut.
+    pub fn compute_appid(&self) -> u32 {
+        let mut buf = Vec::with_capacity(self.exe.len() + self.app_name.len() + 2);
+        buf.extend_from_slice(format!("\"{}\"", self.exe).as_bytes());
+        buf.extend_from_slice(self.app_name.as_bytes());
+        crc32(&buf) | 0x8000_0000
+    }
+
+    /// The full 64-bit id Steam stores for the shortcut, used to build
+    /// `steam://rungameid/<id>` launch URIs.
+    pub fn compute_appid64(&self) -> u64 {
+        ((self.compute_appid() as u64) << 32) | 0x0200_0000
+    }
+
+    /// The "big picture" id used in some Steam paths.
+    pub fn big_picture_appid(&self) -> u64 {
+        self.compute_appid64() >> 32
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn set_id(&mut self, id: u32) {
+        self.id = id;
+    }
+
+    pub fn app_name(&self) -> &str {
+        &self.app_name
+    }
+
+    pub fn set_app_name(&mut self, app_name: &str) {
+        self.app_name = app_name.to_string();
+    }
+
+    pub fn exe(&self) -> &str {
+        &self.exe
+    }
+
+    pub fn set_exe(&mut self, exe: &str) {
+        self.exe = exe.to_string();
+    }
+
+    pub fn start_dir(&self) -> &str {
+        &self.start_dir
+    }
+
+    pub fn set_start_dir(&mut self, start_dir: &str) {
+        self.start_dir = start_dir.to_string();
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.is_hidden
+    }
+
+    pub fn set_is_hidden(&mut self, is_hidden: bool) {
+        self.is_hidden = is_hidden;
+    }
+
+    pub fn icon(&self) -> &str {
+        &self.icon
+    }
+
+    pub fn set_icon(&mut self, icon: &str) {
+        self.icon = icon.to_string();
+    }
+
+    pub fn launch_options(&self) -> &str {
+        &self.launch_options
+    }
+
+    pub fn set_launch_options(&mut self, launch_options: &str) {
+        self.launch_options = launch_options.to_string();
+    }
+
+    pub fn allow_desktop_config(&self) -> bool {
+        self.allow_desktop_config
+    }
+
+    pub fn set_allow_desktop_config(&mut self, allow_desktop_config: bool) {
+        self.allow_desktop_config = allow_desktop_config;
+    }
+
+    pub fn shortcut_path(&self) -> &str {
+        &self.shortcut_path
+    }
+
+    pub fn set_shortcut_path(&mut self, shortcut_path: &str) {
+        self.shortcut_path = shortcut_path.to_string();
+    }
+
+    pub fn last_play_time(&self) -> SystemTime {
+        self.last_play_time
+    }
+
+    pub fn set_last_play_time(&mut self, last_play_time: SystemTime) {
+        self.last_play_time = last_play_time;
+    }
+
+    pub fn open_vr(&self) -> bool {
+        self.open_vr
+    }
+
+    pub fn set_open_vr(&mut self, open_vr: bool) {
+        self.open_vr = open_vr;
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = tags;
+    }
+}
+
+/// Fluent alternative to `Shortcut::new`'s twelve positional arguments.
+/// Unset fields keep `Shortcut`'s `Default` values.
+#[derive(Debug, Clone, Default)]
+pub struct ShortcutBuilder {
+    shortcut: Shortcut,
+}
+
+impl ShortcutBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn app_name(mut self, app_name: &str) -> Self {
+        self.shortcut.set_app_name(app_name);
+        self
+    }
+
+    pub fn exe(mut self, exe: &str) -> Self {
+        self.shortcut.set_exe(exe);
+        self
+    }
+
+    pub fn start_dir(mut self, start_dir: &str) -> Self {
+        self.shortcut.set_start_dir(start_dir);
+        self
+    }
+
+    pub fn is_hidden(mut self, is_hidden: bool) -> Self {
+        self.shortcut.set_is_hidden(is_hidden);
+        self
+    }
+
+    pub fn icon(mut self, icon: &str) -> Self {
+        self.shortcut.set_icon(icon);
+        self
+    }
+
+    pub fn launch_options(mut self, launch_options: &str) -> Self {
+        self.shortcut.set_launch_options(launch_options);
+        self
+    }
+
+    pub fn allow_desktop_config(mut self, allow_desktop_config: bool) -> Self {
+        self.shortcut.set_allow_desktop_config(allow_desktop_config);
+        self
+    }
+
+    pub fn shortcut_path(mut self, shortcut_path: &str) -> Self {
+        self.shortcut.set_shortcut_path(shortcut_path);
+        self
+    }
+
+    pub fn last_play_time(mut self, last_play_time: SystemTime) -> Self {
+        self.shortcut.set_last_play_time(last_play_time);
+        self
+    }
+
+    pub fn open_vr(mut self, open_vr: bool) -> Self {
+        self.shortcut.set_open_vr(open_vr);
+        self
+    }
+
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.shortcut.set_tags(tags);
+        self
+    }
+
+    pub fn build(self) -> Shortcut {
+        self.shortcut
+    }
+}
+
+/// A mutable set of shortcuts, e.g. loaded from `parser::Parser`, that knows
+/// how to add/remove/find entries and write itself back out to a
+/// `shortcuts.vdf`.
+#[derive(Debug, Clone, Default)]
+pub struct ShortcutCollection {
+    shortcuts: Vec<Shortcut>,
+}
+
+impl ShortcutCollection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn shortcuts(&self) -> &[Shortcut] {
+        &self.shortcuts
+    }
+
+    pub fn add(&mut self, mut shortcut: Shortcut) {
+        let appid = shortcut.compute_appid();
+        shortcut.set_id(appid);
+        self.shortcuts.push(shortcut);
+    }
+
+    pub fn remove_by_appid(&mut self, appid: u32) -> Option<Shortcut> {
+        let idx = self.shortcuts.iter().position(|s| s.id() == appid)?;
+        Some(self.shortcuts.remove(idx))
+    }
+
+    pub fn find_by_appid(&self, appid: u32) -> Option<&Shortcut> {
+        self.shortcuts.iter().find(|s| s.id() == appid)
+    }
+
+    /// Recomputes every shortcut's AppID and writes the collection out as a
+    /// binary `shortcuts.vdf` at `path`.
+    pub fn write_all(&mut self, path: &str) -> Result<(), std::io::Error> {
+        for shortcut in self.shortcuts.iter_mut() {
+            let appid = shortcut.compute_appid();
+            shortcut.set_id(appid);
+        }
+
+        Shortcut::write_all(path, &self.shortcuts)
+    }
+}
+
+impl From<parser::Parser> for ShortcutCollection {
+    fn from(parser: parser::Parser) -> Self {
+        Self {
+            shortcuts: parser.collect(),
+        }
+    }
 }
 
-type LooseMap = HashMap<String, Box<dyn std::any::Any>>;
+pub(crate) type LooseMap = HashMap<String, Box<dyn std::any::Any>>;
 
 impl From<&LooseMap> for Shortcut {
     fn from(t: &LooseMap) -> Self {
         Self {
-            id: 0,
-            //id: *t.get("id").unwrap().clone().downcast_ref::<u32>().unwrap(),
+            id: *t.get("appid").unwrap().downcast_ref::<u32>().unwrap(),
             app_name: String::from(
                 (**t.get("AppName").clone().unwrap())
                     .downcast_ref::<String>()
@@ -115,13 +375,31 @@ impl From<&LooseMap> for Shortcut {
                         .downcast_ref::<u32>()
                         .unwrap() as u64,
                 ),
-            tags: Vec::new(),
+            tags: t
+                .get("tags")
+                .map(|tags| {
+                    let tags = tags.downcast_ref::<LooseMap>().unwrap();
+
+                    let mut entries: Vec<(u32, String)> = tags
+                        .iter()
+                        .map(|(idx, val)| {
+                            (
+                                idx.parse::<u32>().unwrap(),
+                                val.downcast_ref::<String>().unwrap().clone(),
+                            )
+                        })
+                        .collect();
+                    entries.sort_by_key(|(idx, _)| *idx);
+
+                    entries.into_iter().map(|(_, val)| val).collect()
+                })
+                .unwrap_or_default(),
         }
     }
 }
 
 pub mod parser {
-    use std::io::{Bytes, Read};
+    use std::io::{Bytes, Read, Write};
     use std::{any::Any, fs::File};
 
     use crate::{LooseMap, Shortcut};
@@ -129,6 +407,9 @@ pub mod parser {
     const TYPE_OBJECT: u8 = 0;
     const TYPE_STRING: u8 = 1;
     const TYPE_INT: u8 = 2;
+    const TYPE_FLOAT: u8 = 3;
+    const TYPE_WSTRING: u8 = 5;
+    const TYPE_INT64: u8 = 7;
 
     const TERMINATOR_SHORTCUT: u8 = 8;
     const TERMINATOR_STRING: u8 = 0; // probably could just use cstr handlers for this
@@ -146,7 +427,39 @@ pub mod parser {
         .expect("invalid UTF-8 in shortcut definition")
     }
 
-    fn parse_object(handle: &mut Bytes<File>) -> Result<Box<LooseMap>, std::io::Error> {
+    pub(crate) fn read_le_bytes<const N: usize>(
+        handle: &mut Bytes<File>,
+    ) -> Result<[u8; N], std::io::Error> {
+        let mut buf = [0u8; N];
+        let mut read = 0;
+
+        for byte in handle.take(N) {
+            buf[read] = byte?;
+            read += 1;
+        }
+
+        if read < N {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof));
+        }
+
+        Ok(buf)
+    }
+
+    fn read_next_wide_string(handle: &mut Bytes<File>) -> Result<String, std::io::Error> {
+        let mut units: Vec<u16> = Vec::new();
+
+        loop {
+            let unit = u16::from_le_bytes(read_le_bytes(handle)?);
+            if unit == 0 {
+                break;
+            }
+            units.push(unit);
+        }
+
+        Ok(String::from_utf16(&units).expect("invalid UTF-16 in shortcut definition"))
+    }
+
+    pub(crate) fn parse_object(handle: &mut Bytes<File>) -> Result<Box<LooseMap>, std::io::Error> {
         let mut loose_map = Box::new(LooseMap::new());
 
         while let Some(Ok(header)) = handle.next() {
@@ -158,26 +471,28 @@ pub mod parser {
 
             match header {
                 TYPE_OBJECT => {
-                    eprintln!("Type: object");
                     let obj = parse_object(handle)?;
                     loose_map.insert(property, obj);
                 }
                 TYPE_STRING => {
                     let val = read_next_string(handle);
-                    loose_map.insert(property, Box::new(val.clone()));
-                    eprintln!("Type: string: {}", val);
+                    loose_map.insert(property, Box::new(val));
                 }
                 TYPE_INT => {
-                    let mut target: u32 = 0;
-
-                    let mut i = 0;
-                    for x in handle.take(4) {
-                        target |= (x? as u32) << i;
-                        i += 1;
-                    }
-
+                    let target = u32::from_le_bytes(read_le_bytes(handle)?);
+                    loose_map.insert(property, Box::new(target));
+                }
+                TYPE_FLOAT => {
+                    let target = f32::from_le_bytes(read_le_bytes(handle)?);
+                    loose_map.insert(property, Box::new(target));
+                }
+                TYPE_WSTRING => {
+                    let val = read_next_wide_string(handle)?;
+                    loose_map.insert(property, Box::new(val));
+                }
+                TYPE_INT64 => {
+                    let target = i64::from_le_bytes(read_le_bytes(handle)?);
                     loose_map.insert(property, Box::new(target));
-                    eprintln!("Type: int: {}", target);
                 }
                 _ => {
                     eprintln!("Unrecognized type {}", header);
@@ -220,12 +535,199 @@ pub mod parser {
         fn next(&mut self) -> Option<Self::Item> {
             if let Some(any) = self.parsed_map.get(&format!("{}", self.idx)) {
                 let map = any.downcast_ref::<LooseMap>().unwrap();
-                let mut sc = Shortcut::from(map);
-                sc.id = self.idx;
+                let sc = Shortcut::from(map);
                 self.idx += 1;
                 return Some(sc);
             }
             None
         }
     }
+
+    fn write_string(handle: &mut File, s: &str) -> Result<(), std::io::Error> {
+        handle.write_all(s.as_bytes())?;
+        handle.write_all(&[TERMINATOR_STRING])
+    }
+
+    fn write_string_prop(handle: &mut File, name: &str, value: &str) -> Result<(), std::io::Error> {
+        handle.write_all(&[TYPE_STRING])?;
+        write_string(handle, name)?;
+        write_string(handle, value)
+    }
+
+    fn write_int_prop(handle: &mut File, name: &str, value: u32) -> Result<(), std::io::Error> {
+        handle.write_all(&[TYPE_INT])?;
+        write_string(handle, name)?;
+        handle.write_all(&value.to_le_bytes())
+    }
+
+    fn write_tags(handle: &mut File, tags: &[String]) -> Result<(), std::io::Error> {
+        handle.write_all(&[TYPE_OBJECT])?;
+        write_string(handle, "tags")?;
+
+        for (idx, tag) in tags.iter().enumerate() {
+            write_string_prop(handle, &idx.to_string(), tag)?;
+        }
+
+        handle.write_all(&[TERMINATOR_SHORTCUT])
+    }
+
+    fn write_shortcut_object(
+        handle: &mut File,
+        index: &str,
+        shortcut: &Shortcut,
+    ) -> Result<(), std::io::Error> {
+        handle.write_all(&[TYPE_OBJECT])?;
+        write_string(handle, index)?;
+
+        write_int_prop(handle, "appid", shortcut.id)?;
+        write_string_prop(handle, "AppName", &shortcut.app_name)?;
+        write_string_prop(handle, "exe", &shortcut.exe)?;
+        write_string_prop(handle, "StartDir", &shortcut.start_dir)?;
+        write_string_prop(handle, "icon", &shortcut.icon)?;
+        write_string_prop(handle, "ShortcutPath", &shortcut.shortcut_path)?;
+        write_string_prop(handle, "LaunchOptions", &shortcut.launch_options)?;
+        write_int_prop(handle, "IsHidden", shortcut.is_hidden as u32)?;
+        write_int_prop(
+            handle,
+            "AllowDesktopConfig",
+            shortcut.allow_desktop_config as u32,
+        )?;
+        write_int_prop(handle, "OpenVR", shortcut.open_vr as u32)?;
+        write_int_prop(
+            handle,
+            "LastPlayTime",
+            shortcut
+                .last_play_time
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as u32,
+        )?;
+        write_tags(handle, &shortcut.tags)?;
+
+        handle.write_all(&[TERMINATOR_SHORTCUT])
+    }
+
+    /// Re-emits a binary `shortcuts.vdf` at `filename` containing `shortcuts`,
+    /// mirroring the layout `Parser` reads back.
+    pub fn write_all(filename: &str, shortcuts: &[Shortcut]) -> Result<(), std::io::Error> {
+        let mut handle = std::fs::File::create(filename)?;
+
+        handle.write_all(&[TYPE_OBJECT])?;
+        write_string(&mut handle, "shortcuts")?;
+
+        for (idx, shortcut) in shortcuts.iter().enumerate() {
+            write_shortcut_object(&mut handle, &idx.to_string(), shortcut)?;
+        }
+
+        handle.write_all(&[TERMINATOR_SHORTCUT])?; // closes "shortcuts"
+        handle.write_all(&[TERMINATOR_SHORTCUT])?; // closes the root object
+
+        Ok(())
+    }
+}
+
+pub mod appinfo {
+    use std::collections::HashMap;
+    use std::io::Read;
+
+    use crate::parser::{parse_object, read_le_bytes};
+    use crate::LooseMap;
+
+    // 0x27 and 0x28 share the older, shorter entry layout; 0x29 inserts an
+    // extra SHA1 just before each entry's KeyValues blob.
+    const MAGIC_V27: u32 = 0x0756_4427;
+    const MAGIC_V28: u32 = 0x0756_4428;
+    const MAGIC_V29: u32 = 0x0756_4429;
+
+    #[derive(Debug)]
+    pub struct AppInfoEntry {
+        pub app_id: u32,
+        pub info_state: u32,
+        pub last_updated: u32,
+        pub pics_token: u64,
+        pub text_vdf_sha1: [u8; 20],
+        pub change_number: u32,
+        pub info: LooseMap,
+    }
+
+    #[derive(Debug)]
+    pub struct AppInfo {
+        pub magic: u32,
+        pub universe: u32,
+        pub entries: Vec<AppInfoEntry>,
+    }
+
+    impl AppInfo {
+        /// Parses Steam's `appinfo.vdf` cache, which reuses the same typed
+        /// KeyValues encoding as `shortcuts.vdf`, into one entry per cached
+        /// app.
+        ///
+        /// Known limitation: real `MAGIC_V29` files also add an 8-byte
+        /// string-table offset after `universe` in the header and store each
+        /// entry's `info` via string-table indices rather than inline
+        /// strings. Neither is implemented here, so v29 files will not parse
+        /// correctly yet even though the magic is recognized.
+        pub fn parse(filename: &str) -> Result<Self, std::io::Error> {
+            let mut handle = std::fs::File::open(filename)?.bytes();
+
+            let magic = u32::from_le_bytes(read_le_bytes(&mut handle)?);
+            let universe = u32::from_le_bytes(read_le_bytes(&mut handle)?);
+
+            let mut entries = Vec::new();
+
+            loop {
+                let app_id = u32::from_le_bytes(read_le_bytes(&mut handle)?);
+                if app_id == 0 {
+                    break;
+                }
+
+                // size of the rest of this entry, not otherwise needed since
+                // every field in it is read explicitly below
+                let _size = u32::from_le_bytes(read_le_bytes(&mut handle)?);
+
+                let info_state = u32::from_le_bytes(read_le_bytes(&mut handle)?);
+                let last_updated = u32::from_le_bytes(read_le_bytes(&mut handle)?);
+                let pics_token = u64::from_le_bytes(read_le_bytes(&mut handle)?);
+                let text_vdf_sha1: [u8; 20] = read_le_bytes(&mut handle)?;
+                let change_number = u32::from_le_bytes(read_le_bytes(&mut handle)?);
+
+                match magic {
+                    MAGIC_V27 | MAGIC_V28 => {}
+                    MAGIC_V29 => {
+                        // v29 adds an extra SHA1 right before the KeyValues blob
+                        let _extra_sha1: [u8; 20] = read_le_bytes(&mut handle)?;
+                    }
+                    _ => {}
+                }
+
+                let info = *parse_object(&mut handle)?;
+
+                entries.push(AppInfoEntry {
+                    app_id,
+                    info_state,
+                    last_updated,
+                    pics_token,
+                    text_vdf_sha1,
+                    change_number,
+                    info,
+                });
+            }
+
+            Ok(Self {
+                magic,
+                universe,
+                entries,
+            })
+        }
+
+        pub fn by_app_id(&self, app_id: u32) -> Option<&AppInfoEntry> {
+            self.entries.iter().find(|e| e.app_id == app_id)
+        }
+
+        /// Collapses the entries into a map from app_id to its decoded info
+        /// tree, for cross-referencing against parsed shortcuts.
+        pub fn into_info_map(self) -> HashMap<u32, LooseMap> {
+            self.entries.into_iter().map(|e| (e.app_id, e.info)).collect()
+        }
+    }
 }